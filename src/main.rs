@@ -5,6 +5,8 @@ use std::hash::Hash;
 
 use image::{ImageBuffer, RgbImage};
 use rand::prelude::*;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
 type Color = [u8; 3];
 type Location = [usize; 2];
@@ -62,6 +64,506 @@ where
     }
 }
 
+/// Which space blending, averaging, and diffusion operate in. `Srgb` works on
+/// the raw gamma-encoded bytes; `Lab` converts to perceptually uniform CIE
+/// L*a*b* first so steps and distances track human perception.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum ColorSpace {
+    Srgb,
+    Lab,
+}
+
+fn srgb_to_linear(v: u8) -> f64 {
+    let v = v as f64 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f64) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let s = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (s * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+// D65 reference white.
+const WHITE: [f64; 3] = [0.95047, 1.0, 1.08883];
+
+fn lab_f(t: f64) -> f64 {
+    let delta = 6.0 / 29.0;
+    if t > delta * delta * delta {
+        t.cbrt()
+    } else {
+        t / (3.0 * delta * delta) + 4.0 / 29.0
+    }
+}
+
+fn lab_f_inv(t: f64) -> f64 {
+    let delta = 6.0 / 29.0;
+    if t > delta {
+        t * t * t
+    } else {
+        3.0 * delta * delta * (t - 4.0 / 29.0)
+    }
+}
+
+fn srgb_to_lab(color: Color) -> [f64; 3] {
+    let [r, g, b] = color.map(srgb_to_linear);
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / WHITE[0];
+    let y = (0.2126 * r + 0.7152 * g + 0.0722 * b) / WHITE[1];
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / WHITE[2];
+    let (fx, fy, fz) = (lab_f(x), lab_f(y), lab_f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn lab_to_srgb(lab: [f64; 3]) -> Color {
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+    let x = WHITE[0] * lab_f_inv(fx);
+    let y = WHITE[1] * lab_f_inv(fy);
+    let z = WHITE[2] * lab_f_inv(fz);
+    let r = 3.2406 * x - 1.5372 * y - 0.4986 * z;
+    let g = -0.9689 * x + 1.8758 * y + 0.0415 * z;
+    let b = 0.0557 * x - 0.2040 * y + 1.0570 * z;
+    [r, g, b].map(linear_to_srgb)
+}
+
+/// Average two colors, halving in the chosen space. `Srgb` keeps the original
+/// odd-bit-preserving byte formula; `Lab` averages each L*a*b* channel.
+fn blend(space: ColorSpace, c1: Color, c2: Color) -> Color {
+    match space {
+        ColorSpace::Srgb => c1.zip(c2).map(|(a, b)| a / 2 + b / 2 + (a & b & 1)),
+        ColorSpace::Lab => {
+            let avg = srgb_to_lab(c1)
+                .zip(srgb_to_lab(c2))
+                .map(|(a, b)| (a + b) / 2.0);
+            lab_to_srgb(avg)
+        }
+    }
+}
+
+/// Perturb `color` by up to `diffusion` per channel, clamping in the chosen
+/// space. In `Lab` the offset and clamp happen on the L*a*b* coordinates.
+fn offset_color<R: Rng>(space: ColorSpace, rng: &mut R, color: Color, diffusion: i16) -> Color {
+    match space {
+        ColorSpace::Srgb => {
+            let color_offset = [
+                rng.gen_range(-diffusion..=diffusion),
+                rng.gen_range(-diffusion..=diffusion),
+                rng.gen_range(-diffusion..=diffusion),
+            ];
+            color
+                .map(|c| c as i16)
+                .zip(color_offset)
+                .map(|(c, off)| (c + off).clamp(0, 255) as u8)
+        }
+        ColorSpace::Lab => {
+            let d = diffusion as f64;
+            let mut lab = srgb_to_lab(color);
+            lab[0] = (lab[0] + rng.gen_range(-d..=d)).clamp(0.0, 100.0);
+            lab[1] = (lab[1] + rng.gen_range(-d..=d)).clamp(-128.0, 127.0);
+            lab[2] = (lab[2] + rng.gen_range(-d..=d)).clamp(-128.0, 127.0);
+            lab_to_srgb(lab)
+        }
+    }
+}
+
+/// The byte-space key used to index and compare boundary cells: identity in
+/// `Srgb`, or L*a*b* packed into bytes so Euclidean distance approximates Lab.
+/// All three channels are offset onto the same unit — one byte step is one
+/// L*a*b* unit — so `color_dist_sq` is an undistorted Lab distance rather than
+/// one that over-weights lightness.
+fn color_key(space: ColorSpace, color: Color) -> Color {
+    match space {
+        ColorSpace::Srgb => color,
+        ColorSpace::Lab => {
+            let lab = srgb_to_lab(color);
+            [
+                lab[0].round().clamp(0.0, 255.0) as u8,
+                (lab[1] + 128.0).round().clamp(0.0, 255.0) as u8,
+                (lab[2] + 128.0).round().clamp(0.0, 255.0) as u8,
+            ]
+        }
+    }
+}
+
+fn color_dist_sq(a: Color, b: Color) -> i32 {
+    a.zip(b)
+        .iter()
+        .map(|&(c1, c2)| {
+            let d = c1 as i32 - c2 as i32;
+            d * d
+        })
+        .sum()
+}
+
+#[derive(Debug)]
+struct KdNode {
+    location: Location,
+    color: Color,
+    axis: usize,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// A static, balanced 3-D k-d tree over boundary cells keyed on color, built in
+/// one batch by repeatedly splitting at the median of the widest channel.
+#[derive(Debug)]
+struct KdTree {
+    nodes: Vec<KdNode>,
+    root: Option<usize>,
+    size: usize,
+}
+
+fn build_kd(nodes: &mut Vec<KdNode>, mut points: Vec<(Location, Color)>) -> Option<usize> {
+    if points.is_empty() {
+        return None;
+    }
+    let mut lo = [255i32; 3];
+    let mut hi = [0i32; 3];
+    for (_, color) in &points {
+        for a in 0..3 {
+            lo[a] = lo[a].min(color[a] as i32);
+            hi[a] = hi[a].max(color[a] as i32);
+        }
+    }
+    let axis = (0..3).max_by_key(|&a| hi[a] - lo[a]).expect("3 axes");
+    points.sort_by_key(|(_, color)| color[axis]);
+    let right_points = points.split_off(points.len() / 2 + 1);
+    let (location, color) = points.pop().expect("nonempty");
+    let left = build_kd(nodes, points);
+    let right = build_kd(nodes, right_points);
+    let index = nodes.len();
+    nodes.push(KdNode {
+        location,
+        color,
+        axis,
+        left,
+        right,
+    });
+    Some(index)
+}
+
+impl KdTree {
+    fn build(points: Vec<(Location, Color)>) -> KdTree {
+        let size = points.len();
+        let mut nodes = Vec::with_capacity(size);
+        let root = build_kd(&mut nodes, points);
+        KdTree { nodes, root, size }
+    }
+    fn len(&self) -> usize {
+        self.size
+    }
+    fn points(&self) -> impl Iterator<Item = (Location, Color)> + '_ {
+        self.nodes.iter().map(|node| (node.location, node.color))
+    }
+    fn nearest(
+        &self,
+        target: Color,
+        live: &HashMap<Location, Color>,
+        best: &mut Option<(i32, Location)>,
+    ) {
+        if let Some(root) = self.root {
+            self.descend(root, target, live, best);
+        }
+    }
+    fn descend(
+        &self,
+        index: usize,
+        target: Color,
+        live: &HashMap<Location, Color>,
+        best: &mut Option<(i32, Location)>,
+    ) {
+        let node = &self.nodes[index];
+        if live.get(&node.location) == Some(&node.color) {
+            let dist = color_dist_sq(node.color, target);
+            if best.map_or(true, |(bd, _)| dist < bd) {
+                *best = Some((dist, node.location));
+            }
+        }
+        let diff = target[node.axis] as i32 - node.color[node.axis] as i32;
+        let (near, far) = if diff < 0 {
+            (node.left, node.right)
+        } else {
+            (node.right, node.left)
+        };
+        if let Some(near) = near {
+            self.descend(near, target, live, best);
+        }
+        if let Some(far) = far {
+            if best.map_or(true, |(bd, _)| diff * diff < bd) {
+                self.descend(far, target, live, best);
+            }
+        }
+    }
+}
+
+/// A dynamic nearest-color index over boundary cells: a forest of static k-d
+/// trees whose sizes are distinct powers of two (mirroring the set bits of the
+/// element count). Insertion rebuilds the run of consecutive full low-order
+/// trees plus the new point into the next larger tree, amortizing to O(log n).
+/// Removals are soft — a cell is live iff `live` still maps it to the stored
+/// color — and once dead nodes exceed half the live count the forest rebuilds.
+#[derive(Debug)]
+struct ColorIndex {
+    trees: Vec<KdTree>,
+    live: HashMap<Location, Color>,
+    total: usize,
+}
+
+impl ColorIndex {
+    fn new() -> ColorIndex {
+        ColorIndex {
+            trees: vec![],
+            live: HashMap::new(),
+            total: 0,
+        }
+    }
+    fn insert(&mut self, location: Location, color: Color) {
+        self.live.insert(location, color);
+        self.total += 1;
+        let mut batch = vec![(location, color)];
+        while let Some(pos) = self.trees.iter().position(|t| t.len() == batch.len()) {
+            let tree = self.trees.remove(pos);
+            batch.extend(tree.points());
+        }
+        self.trees.push(KdTree::build(batch));
+        self.maybe_rebuild();
+    }
+    fn remove(&mut self, location: &Location) {
+        if self.live.remove(location).is_some() {
+            self.maybe_rebuild();
+        }
+    }
+    fn is_empty(&self) -> bool {
+        self.live.is_empty()
+    }
+    fn nearest(&self, target: Color) -> Option<Location> {
+        let mut best = None;
+        for tree in &self.trees {
+            tree.nearest(target, &self.live, &mut best);
+        }
+        best.map(|(_, location)| location)
+    }
+    fn maybe_rebuild(&mut self) {
+        let live = self.live.len();
+        if self.total - live > live / 2 {
+            self.rebuild();
+        }
+    }
+    fn rebuild(&mut self) {
+        let mut rest: Vec<(Location, Color)> =
+            self.live.iter().map(|(&l, &c)| (l, c)).collect();
+        self.total = rest.len();
+        self.trees = vec![];
+        while !rest.is_empty() {
+            let mut power = 1;
+            while power * 2 <= rest.len() {
+                power *= 2;
+            }
+            let tail = rest.split_off(power);
+            self.trees.push(KdTree::build(rest));
+            rest = tail;
+        }
+        self.trees.sort_by_key(|tree| tree.len());
+    }
+}
+
+/// Every color in a cube of `side^3` evenly spaced RGB values, in raster order.
+fn rgb_cube(side: usize) -> Vec<Color> {
+    let mut palette = Vec::with_capacity(side * side * side);
+    let step = |i: usize| {
+        if side <= 1 {
+            0
+        } else {
+            (i * 255 / (side - 1)) as u8
+        }
+    };
+    for r in 0..side {
+        for g in 0..side {
+            for b in 0..side {
+                palette.push([step(r), step(g), step(b)]);
+            }
+        }
+    }
+    palette
+}
+
+/// Fill `location` with `color`, moving it out of the frontier and folding its
+/// color into the neighbor-average of each still-empty neighbor.
+fn place_nearest(
+    size: usize,
+    locations_to_colors: &mut HashMap<Location, Color>,
+    neighbor_sums: &mut HashMap<Location, ([u32; 3], u32)>,
+    index: &mut ColorIndex,
+    space: ColorSpace,
+    location: Location,
+    color: Color,
+) {
+    locations_to_colors.insert(location, color);
+    neighbor_sums.remove(&location);
+    index.remove(&location);
+    let key = color_key(space, color);
+    for direction_offset in vec![[-1, 0], [0, -1], [1, 0], [0, 1]] {
+        let maybe_neighbor = location
+            .map(|l| l as isize)
+            .zip(direction_offset)
+            .map(|(l, d)| l + d);
+        if maybe_neighbor.iter().any(|&l| l < 0 || l >= size as isize) {
+            continue;
+        }
+        let neighbor = maybe_neighbor.map(|l| l as usize);
+        if locations_to_colors.contains_key(&neighbor) {
+            continue;
+        }
+        let (sum, count) = neighbor_sums.entry(neighbor).or_insert(([0, 0, 0], 0));
+        *sum = sum.zip(key).map(|(s, c)| s + c as u32);
+        *count += 1;
+        let average = sum.map(|s| (s / *count) as u8);
+        index.insert(neighbor, average);
+    }
+}
+
+/// Place a palette with each color used at most once: each color goes to the
+/// boundary cell whose filled-neighbor average is nearest to it. The palette is
+/// the RGB cube whose side is the rounded cube root of `size * size`; when that
+/// cube has exactly `size * size` colors, the entire palette is placed, once
+/// each. With more colors than cells, placement stops once the grid is full;
+/// with fewer, the leftover cells stay black. `num_seeds` controls how many
+/// randomly scattered cells prime the frontier; each seed takes its color out
+/// of the palette, and a seed that lands on an already-filled cell is skipped.
+/// With no seeds the frontier starts empty and nothing is placed.
+fn make_image_nearest(size: usize, num_seeds: usize, space: ColorSpace, seed: u64) -> RgbImage {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let side = ((size * size) as f64).cbrt().round() as usize;
+    let mut palette = rgb_cube(side);
+    let mut locations_to_colors: HashMap<Location, Color> = HashMap::new();
+    let mut neighbor_sums: HashMap<Location, ([u32; 3], u32)> = HashMap::new();
+    let mut index = ColorIndex::new();
+    for _ in 0..num_seeds {
+        let location: Location = [rng.gen_range(0..size), rng.gen_range(0..size)];
+        if palette.is_empty() || locations_to_colors.contains_key(&location) {
+            continue;
+        }
+        let color = palette.remove(rng.gen_range(0..palette.len()));
+        place_nearest(
+            size,
+            &mut locations_to_colors,
+            &mut neighbor_sums,
+            &mut index,
+            space,
+            location,
+            color,
+        );
+    }
+    for (count, &color) in palette.iter().enumerate() {
+        if count % ((size * size) / 10).max(1) == 0 {
+            println!("{}: {}/{}", count, locations_to_colors.len(), size * size);
+        }
+        if index.is_empty() {
+            break;
+        }
+        let location = index.nearest(color_key(space, color)).expect("Checked nonempty");
+        place_nearest(
+            size,
+            &mut locations_to_colors,
+            &mut neighbor_sums,
+            &mut index,
+            space,
+            location,
+            color,
+        );
+    }
+    let mut img: RgbImage = ImageBuffer::new(size as u32, size as u32);
+    for (location, color) in locations_to_colors {
+        img.put_pixel(location[0] as u32, location[1] as u32, image::Rgb(color))
+    }
+    img
+}
+
+/// Classic gradient (Perlin) noise on the integer lattice, with a permutation
+/// table seeded deterministically so the field is reproducible from `seed`.
+#[derive(Debug)]
+struct Perlin {
+    perm: Vec<usize>,
+}
+
+const GRADIENTS: [(f64, f64); 8] = [
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (1.0, 1.0),
+    (-1.0, 1.0),
+    (1.0, -1.0),
+    (-1.0, -1.0),
+];
+
+fn smootherstep(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+impl Perlin {
+    fn new(seed: u64) -> Perlin {
+        let mut table: Vec<usize> = (0..256).collect();
+        let mut rng = StdRng::seed_from_u64(seed);
+        table.shuffle(&mut rng);
+        let perm = (0..512).map(|i| table[i % 256]).collect();
+        Perlin { perm }
+    }
+    fn grad(&self, hash: usize, x: f64, y: f64) -> f64 {
+        let (gx, gy) = GRADIENTS[hash & 7];
+        gx * x + gy * y
+    }
+    fn noise(&self, x: f64, y: f64) -> f64 {
+        let xi = (x.floor() as i64 & 255) as usize;
+        let yi = (y.floor() as i64 & 255) as usize;
+        let xf = x - x.floor();
+        let yf = y - y.floor();
+        let (u, v) = (smootherstep(xf), smootherstep(yf));
+        let aa = self.perm[self.perm[xi] + yi];
+        let ab = self.perm[self.perm[xi] + yi + 1];
+        let ba = self.perm[self.perm[xi + 1] + yi];
+        let bb = self.perm[self.perm[xi + 1] + yi + 1];
+        let lerp = |a: f64, b: f64, t: f64| a + t * (b - a);
+        let x1 = lerp(self.grad(aa, xf, yf), self.grad(ba, xf - 1.0, yf), u);
+        let x2 = lerp(
+            self.grad(ab, xf, yf - 1.0),
+            self.grad(bb, xf - 1.0, yf - 1.0),
+            u,
+        );
+        lerp(x1, x2, v)
+    }
+    /// Fractal-sum turbulence: `sum |noise(2^i * f * p)| / 2^i` over octaves.
+    fn turbulence(&self, x: f64, y: f64, octaves: u32, frequency: f64) -> f64 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut freq = frequency;
+        for _ in 0..octaves {
+            sum += self.noise(x * freq, y * freq).abs() * amplitude;
+            freq *= 2.0;
+            amplitude *= 0.5;
+        }
+        sum
+    }
+}
+
+/// Turbulence modulation of the diffusion magnitude: a fractal field sampled at
+/// each pixel so color noise clumps into organic veins rather than uniform static.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Turbulence {
+    octaves: u32,
+    frequency: f64,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn make_image(
     size: usize,
     num_seeds: usize,
@@ -70,34 +572,135 @@ fn make_image(
     halving: f64,
     smoothing: isize,
     fuzz: f64,
+    space: ColorSpace,
+    turbulence: Option<Turbulence>,
     seed: u64,
 ) -> RgbImage {
-    let mut rng = StdRng::seed_from_u64(seed);
-    let mut locations_to_colors: HashMap<Location, Color> = HashMap::new();
-    let mut boundary: VecMap<Location, (Color, usize)> = VecMap::new();
-    for _ in 0..num_seeds {
-        let location: Location = [rng.gen_range(0..size), rng.gen_range(0..size)];
-        let color: Color = rng.gen();
-        boundary.insert(location, (color, 0));
+    let mut generator = Generator::new(
+        size, num_seeds, max, long, halving, smoothing, fuzz, space, turbulence, seed,
+    );
+    generator.run(None);
+    generator.image()
+}
+
+/// The ChaCha stream position, captured explicitly so a checkpoint does not
+/// depend on `rand_chacha`'s optional `serde` feature being enabled. The seed,
+/// stream, and word position fully determine the RNG, so restoring them
+/// reproduces the byte-exact sequence the uninterrupted run would have drawn.
+#[derive(Debug, Serialize, Deserialize)]
+struct RngState {
+    seed: [u8; 32],
+    stream: u64,
+    word_pos: u128,
+}
+
+impl RngState {
+    fn capture(rng: &ChaCha12Rng) -> RngState {
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
     }
-    let mut count = 0;
-    loop {
-        if count % ((size * size) / 10) == 0 {
-            println!("{}: {}/{}", count, locations_to_colors.len(), size * size);
+    fn restore(&self) -> ChaCha12Rng {
+        let mut rng = ChaCha12Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos);
+        rng
+    }
+}
+
+/// A serializable snapshot of a `Generator` mid-run: enough to resume it
+/// deterministically, since the stream is driven entirely by the saved RNG.
+#[derive(Debug, Serialize, Deserialize)]
+struct Checkpoint {
+    size: usize,
+    max: u8,
+    long: u8,
+    halving: f64,
+    smoothing: isize,
+    fuzz: f64,
+    space: ColorSpace,
+    turbulence: Option<Turbulence>,
+    seed: u64,
+    rng: RngState,
+    locations_to_colors: Vec<(Location, Color)>,
+    boundary: Vec<(Location, (Color, usize))>,
+    count: usize,
+}
+
+/// The diffusion growth as an explicit state machine, so a run can be stepped,
+/// checkpointed, resumed, branched with new parameters, or dumped as frames.
+struct Generator {
+    size: usize,
+    max: u8,
+    long: u8,
+    halving: f64,
+    smoothing: isize,
+    fuzz: f64,
+    space: ColorSpace,
+    turbulence: Option<Turbulence>,
+    seed: u64,
+    rng: ChaCha12Rng,
+    perlin: Option<Perlin>,
+    locations_to_colors: HashMap<Location, Color>,
+    boundary: VecMap<Location, (Color, usize)>,
+    count: usize,
+}
+
+impl Generator {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        size: usize,
+        num_seeds: usize,
+        max: u8,
+        long: u8,
+        halving: f64,
+        smoothing: isize,
+        fuzz: f64,
+        space: ColorSpace,
+        turbulence: Option<Turbulence>,
+        seed: u64,
+    ) -> Generator {
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let perlin = turbulence.map(|_| Perlin::new(seed));
+        let mut boundary: VecMap<Location, (Color, usize)> = VecMap::new();
+        for _ in 0..num_seeds {
+            let location: Location = [rng.gen_range(0..size), rng.gen_range(0..size)];
+            let color: Color = rng.gen();
+            boundary.insert(location, (color, 0));
         }
-        count += 1;
-        if boundary.is_empty() {
-            break;
+        Generator {
+            size,
+            max,
+            long,
+            halving,
+            smoothing,
+            fuzz,
+            space,
+            turbulence,
+            seed,
+            rng,
+            perlin,
+            locations_to_colors: HashMap::new(),
+            boundary,
+            count: 0,
         }
-        let (location, (color, steps)) = boundary
-            .rand_remove(&mut rng, fuzz)
+    }
+
+    /// Fill one boundary cell and splash its neighbors onto the frontier.
+    fn step(&mut self) {
+        let space = self.space;
+        let (location, (color, steps)) = self
+            .boundary
+            .rand_remove(&mut self.rng, self.fuzz)
             .expect("Checked nonempty");
-        locations_to_colors
+        self.locations_to_colors
             .entry(location)
-            .and_modify(|c| *c = c.zip(color).map(|(c1, c2)| c1 / 2 + c2 / 2 + (c1 & c2 & 1)))
+            .and_modify(|c| *c = blend(space, *c, color))
             .or_insert(color);
         for direction_offset in vec![[-1, 0], [0, -1], [1, 0], [0, 1]] {
-            if rng.gen::<f64>() > 0.5 {
+            if self.rng.gen::<f64>() > 0.5 {
                 continue;
             }
             let maybe_new_location = location
@@ -106,23 +709,23 @@ fn make_image(
                 .map(|(l, d)| l + d);
             if maybe_new_location
                 .iter()
-                .any(|&l| l < 0 || l >= size as isize)
+                .any(|&l| l < 0 || l >= self.size as isize)
             {
                 continue;
             }
             let new_location = maybe_new_location.map(|l| l as usize);
-            if locations_to_colors.contains_key(&new_location) {
+            if self.locations_to_colors.contains_key(&new_location) {
                 let mut found_empty = false;
-                for off in -smoothing..=smoothing {
+                for off in -self.smoothing..=self.smoothing {
                     for (dr, dc) in vec![(0, off), (off, 0), (off, off), (-off, off)] {
                         let nr = dr + new_location[0] as isize;
                         let nc = dc + new_location[1] as isize;
-                        if nr < 0 || nr >= size as isize || nc < 0 || nc >= size as isize {
+                        if nr < 0 || nr >= self.size as isize || nc < 0 || nc >= self.size as isize {
                             continue;
                         }
                         let nr = nr as usize;
                         let nc = nc as usize;
-                        if !locations_to_colors.contains_key(&[nr, nc]) {
+                        if !self.locations_to_colors.contains_key(&[nr, nc]) {
                             found_empty = true;
                             break;
                         }
@@ -132,35 +735,449 @@ fn make_image(
                     continue;
                 }
             }
-            let fdiffusion =
-                (max - long) as f64 * 2.0f64.powf(-(steps as f64) / halving) + long as f64;
+            let mut fdiffusion = (self.max - self.long) as f64
+                * 2.0f64.powf(-(steps as f64) / self.halving)
+                + self.long as f64;
+            if let (Some(perlin), Some(turbulence)) = (&self.perlin, self.turbulence) {
+                fdiffusion *= perlin.turbulence(
+                    new_location[0] as f64,
+                    new_location[1] as f64,
+                    turbulence.octaves,
+                    turbulence.frequency,
+                );
+            }
             let diffusion = fdiffusion as i16;
-            let color_offset = [
-                rng.gen_range(-diffusion..=diffusion),
-                rng.gen_range(-diffusion..=diffusion),
-                rng.gen_range(-diffusion..=diffusion),
-            ];
-            let new_color = color
-                .map(|c| c as i16)
-                .zip(color_offset)
-                .map(|(c, off)| (c + off).clamp(0, 255) as u8);
-            boundary.insert_modify(
+            let new_color = offset_color(space, &mut self.rng, color, diffusion);
+            self.boundary.insert_modify(
                 new_location,
                 (new_color, steps + 1),
                 |(old_color, old_steps)| {
-                    *old_color = old_color
-                        .zip(new_color)
-                        .map(|(c1, c2)| c1 / 2 + c2 / 2 + (c1 & c2 & 1));
+                    *old_color = blend(space, *old_color, new_color);
                     *old_steps = (*old_steps + steps + 1) / 2;
                 },
             );
         }
     }
-    let mut img: RgbImage = ImageBuffer::new(size as u32, size as u32);
-    for (location, color) in locations_to_colors {
-        img.put_pixel(location[0] as u32, location[1] as u32, image::Rgb(color))
+
+    /// Run to completion. When `frames` is `Some((n, dir))`, dump the current
+    /// image to `dir` every `n` iterations so the growth can be assembled into
+    /// an animation.
+    fn run(&mut self, frames: Option<(usize, &str)>) {
+        loop {
+            if self.count % ((self.size * self.size) / 10) == 0 {
+                println!(
+                    "{}: {}/{}",
+                    self.count,
+                    self.locations_to_colors.len(),
+                    self.size * self.size
+                );
+            }
+            self.count += 1;
+            if self.boundary.is_empty() {
+                break;
+            }
+            self.step();
+            if let Some((n, dir)) = frames {
+                if self.count % n == 0 {
+                    self.image()
+                        .save(format!("{}/frame-{:08}.png", dir, self.count))
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    fn image(&self) -> RgbImage {
+        let mut img: RgbImage = ImageBuffer::new(self.size as u32, self.size as u32);
+        for (&location, &color) in &self.locations_to_colors {
+            img.put_pixel(location[0] as u32, location[1] as u32, image::Rgb(color))
+        }
+        img
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            size: self.size,
+            max: self.max,
+            long: self.long,
+            halving: self.halving,
+            smoothing: self.smoothing,
+            fuzz: self.fuzz,
+            space: self.space,
+            turbulence: self.turbulence,
+            seed: self.seed,
+            rng: RngState::capture(&self.rng),
+            locations_to_colors: self.locations_to_colors.iter().map(|(&l, &c)| (l, c)).collect(),
+            // Preserve `boundary.vec`'s order — `rand_remove` indexes into it,
+            // so a resume is only bit-exact if the order survives the round-trip.
+            boundary: self
+                .boundary
+                .vec
+                .iter()
+                .map(|&l| (l, self.boundary.map[&l]))
+                .collect(),
+            count: self.count,
+        }
+    }
+
+    fn save(&self, path: &str) {
+        let json = serde_json::to_string(&self.checkpoint()).expect("serializable");
+        std::fs::write(path, json).expect("writable checkpoint");
+    }
+
+    fn restore(checkpoint: Checkpoint) -> Generator {
+        let perlin = checkpoint.turbulence.map(|_| Perlin::new(checkpoint.seed));
+        let mut boundary = VecMap::new();
+        for (location, value) in checkpoint.boundary {
+            boundary.insert(location, value);
+        }
+        Generator {
+            size: checkpoint.size,
+            max: checkpoint.max,
+            long: checkpoint.long,
+            halving: checkpoint.halving,
+            smoothing: checkpoint.smoothing,
+            fuzz: checkpoint.fuzz,
+            space: checkpoint.space,
+            turbulence: checkpoint.turbulence,
+            seed: checkpoint.seed,
+            rng: checkpoint.rng.restore(),
+            perlin,
+            locations_to_colors: checkpoint.locations_to_colors.into_iter().collect(),
+            boundary,
+            count: checkpoint.count,
+        }
+    }
+
+    fn load(path: &str) -> Generator {
+        let json = std::fs::read_to_string(path).expect("readable checkpoint");
+        let checkpoint = serde_json::from_str(&json).expect("valid checkpoint");
+        Generator::restore(checkpoint)
     }
-    img
+}
+
+/// The axis with the widest spread in a box, and that spread.
+fn longest_axis(pixels: &[Color]) -> (usize, u8) {
+    (0..3)
+        .map(|a| {
+            let lo = pixels.iter().map(|c| c[a]).min().unwrap_or(0);
+            let hi = pixels.iter().map(|c| c[a]).max().unwrap_or(0);
+            (a, hi - lo)
+        })
+        .max_by_key(|&(_, spread)| spread)
+        .expect("3 axes")
+}
+
+fn mean_color(pixels: &[Color]) -> Color {
+    let mut sum = [0u64; 3];
+    for color in pixels {
+        for a in 0..3 {
+            sum[a] += color[a] as u64;
+        }
+    }
+    sum.map(|s| (s / pixels.len().max(1) as u64) as u8)
+}
+
+/// Median-cut: start with one box over all pixels, repeatedly split the box
+/// whose longest axis times population is largest at the median of that axis,
+/// until `k` boxes remain. Each box's palette entry is its mean color.
+fn median_cut(pixels: &[Color], k: usize) -> Vec<Color> {
+    let mut boxes = vec![pixels.to_vec()];
+    while boxes.len() < k {
+        let target = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .max_by_key(|(_, b)| longest_axis(b).1 as usize * b.len());
+        let index = match target {
+            Some((index, _)) => index,
+            None => break,
+        };
+        let mut current = boxes.swap_remove(index);
+        let (axis, _) = longest_axis(&current);
+        current.sort_by_key(|c| c[axis]);
+        let right = current.split_off(current.len() / 2);
+        boxes.push(current);
+        boxes.push(right);
+    }
+    boxes.iter().map(|b| mean_color(b)).collect()
+}
+
+fn nearest_palette(palette_keys: &[Color], space: ColorSpace, color: Color) -> usize {
+    let key = color_key(space, color);
+    palette_keys
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &pk)| color_dist_sq(pk, key))
+        .map(|(index, _)| index)
+        .expect("nonempty palette")
+}
+
+/// An indexed image: one palette index per pixel plus the palette it refers
+/// into. Saving this writes a genuinely paletted PNG — each pixel is a single
+/// index byte, not an RGB triple — which is what shrinks the file.
+struct IndexedImage {
+    width: u32,
+    height: u32,
+    palette: Vec<Color>,
+    indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    fn save(&self, path: &str) {
+        let file = std::fs::File::create(path).expect("writable png");
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        let palette: Vec<u8> = self.palette.iter().flat_map(|c| c.iter().copied()).collect();
+        encoder.set_palette(palette);
+        let mut writer = encoder.write_header().expect("png header");
+        writer.write_image_data(&self.indices).expect("png data");
+    }
+}
+
+/// Quantize to a `k`-color median-cut palette, remapping each pixel to the
+/// index of its nearest palette color (in `space`), optionally spreading the
+/// quantization error to neighbors with Floyd–Steinberg weights. The result is
+/// an indexed image keyed by palette index, not reduced-color truecolor.
+fn quantize(img: &RgbImage, k: usize, space: ColorSpace, dither: bool) -> IndexedImage {
+    assert!(k <= 256, "indexed PNG holds at most 256 palette entries");
+    let (width, height) = img.dimensions();
+    let pixels: Vec<Color> = img.pixels().map(|p| p.0).collect();
+    let palette = median_cut(&pixels, k);
+    let palette_keys: Vec<Color> = palette.iter().map(|&c| color_key(space, c)).collect();
+    let mut indices = vec![0u8; (width * height) as usize];
+    let mut error = vec![[0.0f64; 3]; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) as usize;
+            let original = img.get_pixel(x, y).0;
+            let actual = original
+                .map(|c| c as f64)
+                .zip(error[index])
+                .map(|(c, e)| (c + e).clamp(0.0, 255.0));
+            let actual_color = actual.map(|v| v.round() as u8);
+            let palette_index = nearest_palette(&palette_keys, space, actual_color);
+            let chosen = palette[palette_index];
+            indices[index] = palette_index as u8;
+            if dither {
+                let residual = actual.zip(chosen).map(|(a, c)| a - c as f64);
+                let mut spread = |nx: i64, ny: i64, weight: f64| {
+                    if nx >= 0 && nx < width as i64 && ny >= 0 && ny < height as i64 {
+                        let ni = (ny as u32 * width + nx as u32) as usize;
+                        for a in 0..3 {
+                            error[ni][a] += residual[a] * weight;
+                        }
+                    }
+                };
+                let (x, y) = (x as i64, y as i64);
+                spread(x + 1, y, 7.0 / 16.0);
+                spread(x - 1, y + 1, 3.0 / 16.0);
+                spread(x, y + 1, 5.0 / 16.0);
+                spread(x + 1, y + 1, 1.0 / 16.0);
+            }
+        }
+    }
+    IndexedImage {
+        width,
+        height,
+        palette,
+        indices,
+    }
+}
+
+/// Separable-ish Gaussian window weights over a `(2r+1)^2` neighborhood.
+fn gaussian_window(radius: i32, sigma: f64) -> Vec<Vec<f64>> {
+    (-radius..=radius)
+        .map(|dy| {
+            (-radius..=radius)
+                .map(|dx| (-((dx * dx + dy * dy) as f64) / (2.0 * sigma * sigma)).exp())
+                .collect()
+        })
+        .collect()
+}
+
+fn to_lab_plane(img: &RgbImage) -> (usize, usize, Vec<[f64; 3]>) {
+    let (width, height) = img.dimensions();
+    let lab = img.pixels().map(|p| srgb_to_lab(p.0)).collect();
+    (width as usize, height as usize, lab)
+}
+
+/// Box-downsample a Lab plane by a factor of two.
+fn downsample(width: usize, height: usize, lab: &[[f64; 3]]) -> (usize, usize, Vec<[f64; 3]>) {
+    let (nw, nh) = (width / 2, height / 2);
+    let mut out = vec![[0.0; 3]; nw * nh];
+    for y in 0..nh {
+        for x in 0..nw {
+            let mut sum = [0.0; 3];
+            for (dy, dx) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                let src = lab[(2 * y + dy) * width + (2 * x + dx)];
+                for a in 0..3 {
+                    sum[a] += src[a] / 4.0;
+                }
+            }
+            out[y * nw + x] = sum;
+        }
+    }
+    (nw, nh, out)
+}
+
+/// Mean SSIM over a single scale: per-channel local means, variances, and
+/// covariance under a Gaussian window, combined by the SSIM formula.
+fn ssim_scale(width: usize, height: usize, a: &[[f64; 3]], b: &[[f64; 3]]) -> f64 {
+    let radius = 3;
+    let weights = gaussian_window(radius, 1.5);
+    let c1 = (0.01 * 100.0f64).powi(2);
+    let c2 = (0.03 * 100.0f64).powi(2);
+    let mut total = 0.0;
+    let mut count = 0.0;
+    for y in 0..height {
+        for x in 0..width {
+            for ch in 0..3 {
+                let (mut mx, mut my, mut wsum) = (0.0, 0.0, 0.0);
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                        if xx < 0 || xx >= width as i32 || yy < 0 || yy >= height as i32 {
+                            continue;
+                        }
+                        let wt = weights[(dy + radius) as usize][(dx + radius) as usize];
+                        let idx = yy as usize * width + xx as usize;
+                        mx += wt * a[idx][ch];
+                        my += wt * b[idx][ch];
+                        wsum += wt;
+                    }
+                }
+                mx /= wsum;
+                my /= wsum;
+                let (mut vx, mut vy, mut vxy) = (0.0, 0.0, 0.0);
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                        if xx < 0 || xx >= width as i32 || yy < 0 || yy >= height as i32 {
+                            continue;
+                        }
+                        let wt = weights[(dy + radius) as usize][(dx + radius) as usize];
+                        let idx = yy as usize * width + xx as usize;
+                        let (da, db) = (a[idx][ch] - mx, b[idx][ch] - my);
+                        vx += wt * da * da;
+                        vy += wt * db * db;
+                        vxy += wt * da * db;
+                    }
+                }
+                vx /= wsum;
+                vy /= wsum;
+                vxy /= wsum;
+                let ssim = ((2.0 * mx * my + c1) * (2.0 * vxy + c2))
+                    / ((mx * mx + my * my + c1) * (vx + vy + c2));
+                total += ssim;
+                count += 1.0;
+            }
+        }
+    }
+    total / count
+}
+
+/// Multi-scale structural dissimilarity in Lab: mean SSIM across a few
+/// downsampling octaves, folded into a single "worse = larger" score.
+fn dssim(a: &RgbImage, b: &RgbImage) -> f64 {
+    let (mut width, mut height, mut la) = to_lab_plane(a);
+    let (_, _, mut lb) = to_lab_plane(b);
+    let octaves = 3;
+    let mut sum = 0.0;
+    let mut used = 0;
+    for _ in 0..octaves {
+        if width < 8 || height < 8 {
+            break;
+        }
+        sum += ssim_scale(width, height, &la, &lb);
+        used += 1;
+        let (nw, nh, da) = downsample(width, height, &la);
+        let (_, _, db) = downsample(width, height, &lb);
+        width = nw;
+        height = nh;
+        la = da;
+        lb = db;
+    }
+    let mean_ssim = if used == 0 { 1.0 } else { sum / used as f64 };
+    1.0 / mean_ssim - 1.0
+}
+
+/// The tunable inputs to `make_image` that the search explores.
+#[derive(Debug, Clone, Copy)]
+struct Params {
+    num_seeds: usize,
+    max: u8,
+    long: u8,
+    halving: f64,
+    smoothing: isize,
+    fuzz: f64,
+    seed: u64,
+}
+
+fn render(size: usize, params: Params, space: ColorSpace) -> RgbImage {
+    make_image(
+        size,
+        params.num_seeds,
+        params.max,
+        params.long,
+        params.halving,
+        params.smoothing,
+        params.fuzz,
+        space,
+        None,
+        params.seed,
+    )
+}
+
+fn mutate<R: Rng>(params: &mut Params, rng: &mut R) {
+    match rng.gen_range(0..7) {
+        0 => {
+            params.num_seeds =
+                (params.num_seeds as i64 + rng.gen_range(-2..=2)).clamp(1, 1000) as usize
+        }
+        1 => params.max = params.max.saturating_add_signed(rng.gen_range(-16..=16)),
+        2 => params.long = params.long.saturating_add_signed(rng.gen_range(-4..=4)).min(params.max),
+        3 => params.halving = (params.halving + rng.gen_range(-1.0..=1.0)).clamp(0.5, 16.0),
+        4 => params.smoothing = (params.smoothing + rng.gen_range(-1..=1)).clamp(0, 8),
+        5 => params.fuzz = (params.fuzz + rng.gen_range(-0.1..=0.1)).clamp(0.0, 1.0),
+        _ => params.seed = rng.gen(),
+    }
+}
+
+/// Randomized hill-climb tuning `make_image`'s parameters to minimize the DSSIM
+/// against `target`. Returns the best parameters found and their render.
+fn search(
+    target: &RgbImage,
+    space: ColorSpace,
+    iterations: usize,
+    seed: u64,
+) -> (Params, RgbImage) {
+    let size = target.width() as usize;
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut best = Params {
+        num_seeds: 10,
+        max: 255,
+        long: 6,
+        halving: 4.0,
+        smoothing: 4,
+        fuzz: 0.8,
+        seed: 1,
+    };
+    let mut best_img = render(size, best, space);
+    let mut best_score = dssim(target, &best_img);
+    for _ in 0..iterations {
+        let mut candidate = best;
+        mutate(&mut candidate, &mut rng);
+        let img = render(size, candidate, space);
+        let score = dssim(target, &img);
+        if score < best_score {
+            best = candidate;
+            best_img = img;
+            best_score = score;
+        }
+    }
+    (best, best_img)
 }
 
 fn main() {
@@ -171,21 +1188,169 @@ fn main() {
     let halving = 4;
     let smoothing = 4;
     let fuzz = 0.8;
+    let space = ColorSpace::Srgb;
+    let turbulence = None;
+    let palette_size: Option<usize> = None;
+    let dither = true;
     let seed = 1;
+    // Growth mode: diffuse random offsets from seeds (default), or place an
+    // entire downsampled RGB cube exactly once by nearest-color matching.
+    let nearest = false;
+    // Target matching: when set, hill-climb `make_image`'s parameters to
+    // minimize the DSSIM against this reference image instead of rendering
+    // from the fixed parameters above.
+    let target_path: Option<&str> = None;
+    let search_iterations = 100;
+    // Checkpoint/resume and frame export: resume from a saved checkpoint
+    // instead of a fresh frontier, save one when the run finishes, and dump a
+    // frame every N iterations so the growth can be assembled into an animation.
+    let resume_from: Option<&str> = None;
+    let save_to: Option<&str> = None;
+    let frames: Option<(usize, &str)> = None;
     let filename = format!(
         "img-{}-{}-{}-{}-{}-{}-{}-{}.png",
         size, num_seeds, max, long, halving, smoothing, fuzz, seed
     );
     println!("Start {}", filename);
-    let img = make_image(
-        size,
-        num_seeds,
-        max,
-        long,
-        halving as f64,
-        smoothing,
-        fuzz,
-        seed,
-    );
-    img.save(&filename).unwrap();
+    let img = if let Some(path) = target_path {
+        let target = image::open(path).expect("target image").to_rgb8();
+        let (params, best) = search(&target, space, search_iterations, seed);
+        println!("Best params: {:?}", params);
+        best
+    } else if nearest {
+        make_image_nearest(size, num_seeds, space, seed)
+    } else {
+        let mut generator = match resume_from {
+            Some(path) => Generator::load(path),
+            None => Generator::new(
+                size,
+                num_seeds,
+                max,
+                long,
+                halving as f64,
+                smoothing,
+                fuzz,
+                space,
+                turbulence,
+                seed,
+            ),
+        };
+        generator.run(frames);
+        if let Some(path) = save_to {
+            generator.save(path);
+        }
+        generator.image()
+    };
+    match palette_size {
+        Some(k) => quantize(&img, k, space, dither).save(&filename),
+        None => img.save(&filename).unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// sRGB -> Lab -> sRGB should reproduce every byte triple it's given: the
+    /// conversions are exact inverses up to rounding.
+    #[test]
+    fn lab_srgb_round_trip() {
+        for &color in &[
+            [0, 0, 0],
+            [255, 255, 255],
+            [128, 64, 32],
+            [10, 200, 150],
+            [200, 10, 90],
+        ] {
+            let back = lab_to_srgb(srgb_to_lab(color));
+            for a in 0..3 {
+                assert!(
+                    (color[a] as i16 - back[a] as i16).abs() <= 1,
+                    "{:?} round-tripped to {:?}",
+                    color,
+                    back
+                );
+            }
+        }
+    }
+
+    /// The k-d forest must return the same nearest distance as a brute-force
+    /// scan of the live set, through a churn of insertions and soft removals
+    /// that forces tree merges and a rebuild.
+    #[test]
+    fn kd_forest_matches_brute_force() {
+        fn brute(points: &[(Location, Color)], target: Color) -> Option<i32> {
+            points
+                .iter()
+                .map(|&(_, color)| color_dist_sq(color, target))
+                .min()
+        }
+        // A deterministic pseudo-random spread of colors, no rng dependency.
+        let points: Vec<(Location, Color)> = (0..200usize)
+            .map(|i| {
+                let r = ((i * 37 + 11) % 256) as u8;
+                let g = ((i * 89 + 7) % 256) as u8;
+                let b = ((i * 151 + 29) % 256) as u8;
+                ([i, i], [r, g, b])
+            })
+            .collect();
+        let mut index = ColorIndex::new();
+        let mut live: Vec<(Location, Color)> = vec![];
+        for (step, &(location, color)) in points.iter().enumerate() {
+            index.insert(location, color);
+            live.push((location, color));
+            // Remove the oldest point periodically, exercising soft deletion
+            // and the rebuild threshold.
+            if step >= 3 && step % 2 == 0 {
+                let (removed, _) = live.remove(0);
+                index.remove(&removed);
+            }
+            for t in 0..8u32 {
+                let target = [(t * 31) as u8, (t * 67) as u8, (t * 101) as u8];
+                assert_eq!(
+                    index.nearest(target).map(|l| color_dist_sq(
+                        live.iter().find(|&&(ll, _)| ll == l).unwrap().1,
+                        target
+                    )),
+                    brute(&live, target),
+                    "mismatch at step {} target {:?}",
+                    step,
+                    target
+                );
+            }
+        }
+    }
+
+    /// An image is structurally identical to itself, so its DSSIM against
+    /// itself is zero (mean SSIM of one over every scale).
+    #[test]
+    fn dssim_identity_is_zero() {
+        let mut img: RgbImage = ImageBuffer::new(32, 32);
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            *pixel = image::Rgb([(x * 7) as u8, (y * 5) as u8, ((x + y) * 3) as u8]);
+        }
+        assert!(dssim(&img, &img).abs() < 1e-9);
+    }
+
+    /// Checkpointing mid-run and restoring must reproduce the uninterrupted
+    /// run exactly — the frontier ordering that `rand_remove` depends on has to
+    /// survive the round-trip.
+    #[test]
+    fn checkpoint_resume_is_bit_exact() {
+        let fresh = || Generator::new(16, 5, 255, 6, 4.0, 2, 0.8, ColorSpace::Srgb, None, 7);
+        let mut uninterrupted = fresh();
+        uninterrupted.run(None);
+        let reference = uninterrupted.image();
+
+        let mut partial = fresh();
+        for _ in 0..20 {
+            if partial.boundary.is_empty() {
+                break;
+            }
+            partial.step();
+        }
+        let mut resumed = Generator::restore(partial.checkpoint());
+        resumed.run(None);
+        assert_eq!(resumed.image(), reference);
+    }
 }